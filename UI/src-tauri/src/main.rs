@@ -4,7 +4,10 @@
 )]
 
 use tauri::Manager;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // In release mode, use the Tauri sidecar API.
 // In dev mode, we spawn Python directly (the PyInstaller sidecar can't find
@@ -12,21 +15,417 @@ use std::sync::Mutex;
 #[cfg(not(debug_assertions))]
 use tauri::api::process::{Command, CommandEvent};
 
+/// Initial delay before the first respawn attempt, doubled on every
+/// consecutive failure up to `MAX_BACKOFF`.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// If the API stays up this long, the backoff resets back to `INITIAL_BACKOFF`.
+const BACKOFF_RESET_AFTER: Duration = Duration::from_secs(60);
+/// Give up restarting after this many attempts inside `RESTART_WINDOW`, so a
+/// process that crashes on launch doesn't spin forever.
+const MAX_RESTARTS_PER_WINDOW: u32 = 8;
+const RESTART_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// How long we give the API to exit on its own after a graceful shutdown
+/// request before we escalate to a hard kill.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(3);
+/// How many recent log lines we keep around for `get_api_logs`, so a
+/// diagnostics panel opened after the fact still has something to show.
+const LOG_RING_CAPACITY: usize = 500;
+
+/// How often to poll `/health` while waiting for uvicorn to come up.
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// How long we'll wait before giving up and showing an error surface instead
+/// of a window stuck on a blank page.
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+/// Label of the main window, as declared in `tauri.conf.json`.
+const MAIN_WINDOW: &str = "main";
+
+/// Asks the OS for a free ephemeral port by binding to port 0, then releases
+/// it immediately so the API can bind it instead. Avoids hard-coding a port
+/// that a second Axiome instance, or an unrelated service, might already hold.
+fn pick_free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .expect("failed to bind an ephemeral port")
+        .local_addr()
+        .expect("bound socket always has a local address")
+        .port()
+}
+
+#[tauri::command]
+fn get_api_base_url(state: tauri::State<ApiState>) -> String {
+    format!("http://127.0.0.1:{}", state.port)
+}
+
+/// One line of API output, forwarded to the frontend over `api://log` and
+/// kept in `ApiState::logs` for `get_api_logs`.
+#[derive(Clone, serde::Serialize)]
+struct LogLine {
+    stream: &'static str,
+    message: String,
+    ts: u64,
+}
+
+impl LogLine {
+    fn new(stream: &'static str, message: String) -> Self {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        // The Tauri process API (and some Python loggers) leave a trailing
+        // `\r` on lines; normalize dev and release output the same way.
+        let message = message.trim_end_matches('\r').to_string();
+        Self { stream, message, ts }
+    }
+}
+
+/// Tracks respawn bookkeeping so the supervisor can back off and eventually
+/// give up instead of crash-looping.
+struct RestartState {
+    backoff: Duration,
+    last_start: Option<Instant>,
+    attempts_in_window: u32,
+    window_start: Instant,
+}
+
+impl Default for RestartState {
+    fn default() -> Self {
+        Self {
+            backoff: INITIAL_BACKOFF,
+            last_start: None,
+            attempts_in_window: 0,
+            window_start: Instant::now(),
+        }
+    }
+}
+
 /// Holds the API child process handle so we can kill it on exit.
 struct ApiState {
     #[cfg(debug_assertions)]
     child: Mutex<Option<std::process::Child>>,
     #[cfg(not(debug_assertions))]
     child: Mutex<Option<tauri::api::process::CommandChild>>,
+    restart: Mutex<RestartState>,
+    /// Set while we're tearing the app down so the supervisor doesn't try to
+    /// respawn a process we just killed on purpose.
+    shutting_down: AtomicBool,
+    /// Flipped by the supervisor as soon as it observes the child has exited,
+    /// so the shutdown handshake can poll for a clean exit without needing
+    /// its own `CommandEvent` plumbing. Dev mode polls `try_wait()` directly
+    /// instead (see `graceful_shutdown`), so this only matters in release.
+    #[cfg(not(debug_assertions))]
+    child_exited: AtomicBool,
+    /// How long to wait for a clean exit after a graceful shutdown request
+    /// before escalating to `reap_process_tree`.
+    grace_period: Duration,
+    /// Ring buffer of the last `LOG_RING_CAPACITY` API output lines.
+    logs: Mutex<VecDeque<LogLine>>,
+    /// Ephemeral port the API was told to bind, picked once at startup.
+    port: u16,
+}
+
+/// Records a log line and forwards it to the frontend. Shared by the dev
+/// reader threads and the release `CommandEvent` loop so both paths produce
+/// identical `LogLine`s.
+fn push_log(app_handle: &tauri::AppHandle, stream: &'static str, message: String) {
+    let line = LogLine::new(stream, message);
+
+    let state: tauri::State<ApiState> = app_handle.state();
+    let mut logs = state.logs.lock().unwrap();
+    if logs.len() >= LOG_RING_CAPACITY {
+        logs.pop_front();
+    }
+    logs.push_back(line.clone());
+    drop(logs);
+
+    let _ = app_handle.emit_all("api://log", line);
+}
+
+#[tauri::command]
+fn get_api_logs(state: tauri::State<ApiState>) -> Vec<LogLine> {
+    state.logs.lock().unwrap().iter().cloned().collect()
+}
+
+#[cfg(debug_assertions)]
+fn spawn_dev_child(app_handle: &tauri::AppHandle, api_dir: &std::path::Path, port: u16) -> std::process::Child {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    // Try venv310 first, then venv, then .venv
+    let python = ["venv310", "venv", ".venv"]
+        .iter()
+        .map(|v| api_dir.join(v).join("Scripts").join("python.exe"))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| std::path::PathBuf::from("python"));
+
+    println!("[dev] Starting API server from {:?} on port {}", api_dir, port);
+    println!("[dev] Using Python: {:?}", python);
+
+    let mut cmd = std::process::Command::new(&python);
+    cmd.args(["-m", "uvicorn", "app.main:app", "--reload", "--port", &port.to_string()])
+        .current_dir(api_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // Put uvicorn (and, via --reload, its reloader child) in its own process
+    // group so `reap_process_tree` can signal the whole tree instead of just
+    // the immediate child.
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .expect("Failed to start API server. Is Python venv set up?");
+
+    println!("[dev] API server started (PID {})", child.id());
+
+    // Forward stdout/stderr to the frontend through the same `push_log` path
+    // the release sidecar uses, so both feed an identical log stream.
+    for (stream, pipe) in [
+        ("stdout", child.stdout.take().map(|s| Box::new(s) as Box<dyn std::io::Read + Send>)),
+        ("stderr", child.stderr.take().map(|s| Box::new(s) as Box<dyn std::io::Read + Send>)),
+    ] {
+        if let Some(pipe) = pipe {
+            let app_handle = app_handle.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                    push_log(&app_handle, stream, line);
+                }
+            });
+        }
+    }
+
+    child
+}
+
+/// Kills the API process and, on Unix, every process in its group. In dev,
+/// we spawned uvicorn with `process_group(0)`, so its pgid equals its pid
+/// and `-pgid` reaches the `--reload` reloader's worker child too. The
+/// release sidecar isn't given its own process group (Tauri's sidecar API
+/// doesn't expose `process_group`), so `-pgid` there is a harmless no-op;
+/// the direct, non-negated `pid` signal is what actually stops it. Either
+/// way this is best-effort: errors are ignored, not propagated, since it
+/// only ever runs during teardown.
+fn reap_process_tree(pid: u32) {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .output();
+    }
+
+    #[cfg(unix)]
+    {
+        let pgid = pid as libc::pid_t;
+        unsafe {
+            libc::kill(-pgid, libc::SIGTERM);
+            libc::kill(pgid, libc::SIGTERM);
+        }
+        std::thread::sleep(Duration::from_millis(200));
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+            libc::kill(pgid, libc::SIGKILL);
+        }
+    }
+
+    println!("Reaped API process tree (PID {})", pid);
+}
+
+/// Emits the current API lifecycle state to the frontend so it can show a
+/// status banner (e.g. "reconnecting to backend...").
+fn emit_status(app: &tauri::AppHandle, status: &str) {
+    let _ = app.emit_all("api://status", status);
+}
+
+/// Asks uvicorn to shut down cleanly instead of killing it outright, so
+/// in-flight SQLite writes/open files get a chance to close.
+///
+/// Dev mode has the real PID (and, thanks to `process_group(0)`, the whole
+/// group), so we send `SIGTERM`/non-forceful `taskkill` to the group. The
+/// release sidecar isn't its own group leader, so we signal just its PID
+/// instead, the same portable mechanism without relying on an app-level
+/// `/shutdown` route that nothing in this codebase implements.
+#[cfg(debug_assertions)]
+fn request_graceful_exit(pid: u32) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGTERM);
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T"])
+            .output();
+    }
+}
+
+#[cfg(not(debug_assertions))]
+fn request_graceful_exit(pid: u32) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as libc::pid_t, libc::SIGTERM);
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string()])
+            .output();
+    }
+}
+
+/// Checks whether uvicorn is actually accepting requests yet, as opposed to
+/// merely having been spawned.
+fn probe_health(port: u16) -> bool {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    let Ok(addr) = format!("127.0.0.1:{port}").parse() else {
+        return false;
+    };
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, READY_POLL_INTERVAL) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(READY_POLL_INTERVAL));
+
+    let request = format!("GET /health HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nConnection: close\r\n\r\n");
+    if stream.write_all(request.as_bytes()).is_err() {
+        return false;
+    }
+
+    let mut buf = [0u8; 32];
+    matches!(stream.read(&mut buf), Ok(n) if n > 0 && buf[..n].starts_with(b"HTTP/1.1 200"))
+}
+
+/// Polls `/health` until the API is serving, then reveals the main window.
+/// Runs on the async runtime so it never blocks the Tauri event loop.
+async fn wait_until_ready(app_handle: tauri::AppHandle) {
+    let port = app_handle.state::<ApiState>().port;
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while Instant::now() < deadline {
+        if probe_health(port) {
+            if let Some(window) = app_handle.get_window(MAIN_WINDOW) {
+                let _ = window.show();
+            }
+            emit_status(&app_handle, "ready");
+            return;
+        }
+        tokio::time::sleep(READY_POLL_INTERVAL).await;
+    }
+
+    println!("[setup] API did not become ready within {:?}", READY_TIMEOUT);
+    // Distinct from the supervisor's "giving-up" (restart budget exhausted):
+    // this means the API never came up at all, which the frontend should
+    // treat as a separate, unrecoverable-without-restart error state.
+    emit_status(&app_handle, "error");
+    // Show the window anyway so the frontend can render an error surface
+    // instead of leaving the user staring at nothing.
+    if let Some(window) = app_handle.get_window(MAIN_WINDOW) {
+        let _ = window.show();
+    }
+}
+
+/// Waits for the API to report it has exited, then hard-kills it if it
+/// overran the grace period. The wait is async, but its only caller drives
+/// it with `block_on` from the window-destroy callback, so that callback's
+/// thread is blocked for up to the grace period.
+async fn graceful_shutdown(app_handle: tauri::AppHandle) {
+    let state: tauri::State<ApiState> = app_handle.state();
+
+    #[cfg(debug_assertions)]
+    let pid = state.child.lock().unwrap().as_ref().map(|c| c.id());
+    #[cfg(not(debug_assertions))]
+    let pid = state.child.lock().unwrap().as_ref().map(|c| c.pid());
+
+    if let Some(pid) = pid {
+        request_graceful_exit(pid);
+    }
+
+    let deadline = Instant::now() + state.grace_period;
+    while Instant::now() < deadline {
+        // In dev there is no `child_exited` flag: the dev supervisor
+        // `break`s on `shutting_down` and never reports the exit. Poll the
+        // child directly here instead.
+        #[cfg(debug_assertions)]
+        let exited = matches!(
+            state.child.lock().unwrap().as_mut().map(|c| c.try_wait()),
+            Some(Ok(Some(_)))
+        );
+        #[cfg(not(debug_assertions))]
+        let exited = state.child_exited.load(Ordering::SeqCst);
+
+        if exited {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    println!("[shutdown] API did not exit within the grace period, forcing");
+    if let Some(child) = state.child.lock().unwrap().take() {
+        #[cfg(debug_assertions)]
+        reap_process_tree(child.id());
+        #[cfg(not(debug_assertions))]
+        reap_process_tree(child.pid());
+    }
+}
+
+/// Records a new restart attempt and returns the backoff to sleep for before
+/// retrying, or `None` if we've given up for this window.
+fn next_backoff(restart: &mut RestartState) -> Option<Duration> {
+    let now = Instant::now();
+
+    if now.duration_since(restart.window_start) > RESTART_WINDOW {
+        restart.window_start = now;
+        restart.attempts_in_window = 0;
+    }
+
+    if let Some(last_start) = restart.last_start {
+        if now.duration_since(last_start) >= BACKOFF_RESET_AFTER {
+            restart.backoff = INITIAL_BACKOFF;
+        }
+    }
+
+    restart.attempts_in_window += 1;
+    if restart.attempts_in_window > MAX_RESTARTS_PER_WINDOW {
+        return None;
+    }
+
+    let wait = restart.backoff;
+    restart.backoff = (restart.backoff * 2).min(MAX_BACKOFF);
+    Some(wait)
 }
 
 fn main() {
+    let port = pick_free_port();
+
     tauri::Builder::default()
         .manage(ApiState {
             child: Mutex::new(None),
+            restart: Mutex::new(RestartState::default()),
+            shutting_down: AtomicBool::new(false),
+            #[cfg(not(debug_assertions))]
+            child_exited: AtomicBool::new(false),
+            grace_period: SHUTDOWN_GRACE_PERIOD,
+            logs: Mutex::new(VecDeque::with_capacity(LOG_RING_CAPACITY)),
+            port,
         })
+        .invoke_handler(tauri::generate_handler![get_api_logs, get_api_base_url])
         .setup(|app| {
-            // -- Dev mode: start Python API directly --
+            let app_handle = app.handle();
+
+            // Ideally the window's `visible` starts `false` in
+            // tauri.conf.json so it never flashes before this runs; hide it
+            // here too so it stays hidden until `wait_until_ready` confirms
+            // the API is actually serving and reveals it.
+            if let Some(window) = app.get_window(MAIN_WINDOW) {
+                let _ = window.hide();
+            }
+
+            // -- Dev mode: start Python API directly, then supervise it --
             #[cfg(debug_assertions)]
             {
                 let api_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -34,44 +433,118 @@ fn main() {
                     .parent().unwrap()   // project root
                     .join("API");
 
-                // Try venv310 first, then venv, then .venv
-                let python = ["venv310", "venv", ".venv"]
-                    .iter()
-                    .map(|v| api_dir.join(v).join("Scripts").join("python.exe"))
-                    .find(|p| p.exists())
-                    .unwrap_or_else(|| std::path::PathBuf::from("python"));
+                let child = spawn_dev_child(&app_handle, &api_dir, port);
 
-                println!("[dev] Starting API server from {:?}", api_dir);
-                println!("[dev] Using Python: {:?}", python);
+                let state: tauri::State<ApiState> = app.state();
+                state.restart.lock().unwrap().last_start = Some(Instant::now());
+                *state.child.lock().unwrap() = Some(child);
+                emit_status(&app_handle, "starting");
+                tauri::async_runtime::spawn(wait_until_ready(app_handle.clone()));
 
-                let child = std::process::Command::new(&python)
-                    .args(["-m", "uvicorn", "app.main:app", "--reload", "--port", "8742"])
-                    .current_dir(&api_dir)
-                    .spawn()
-                    .expect("Failed to start API server. Is Python venv set up?");
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        tokio::time::sleep(Duration::from_millis(500)).await;
 
-                println!("[dev] API server started (PID {})", child.id());
+                        let state: tauri::State<ApiState> = app_handle.state();
+                        if state.shutting_down.load(Ordering::SeqCst) {
+                            break;
+                        }
 
-                let state: tauri::State<ApiState> = app.state();
-                *state.child.lock().unwrap() = Some(child);
+                        let exited = {
+                            let mut guard = state.child.lock().unwrap();
+                            match guard.as_mut() {
+                                Some(child) => matches!(child.try_wait(), Ok(Some(_))),
+                                None => false,
+                            }
+                        };
+                        if !exited {
+                            continue;
+                        }
+
+                        println!("[dev] API process exited unexpectedly");
+                        let wait = next_backoff(&mut state.restart.lock().unwrap());
+                        match wait {
+                            Some(wait) => {
+                                emit_status(&app_handle, "crashed");
+                                tokio::time::sleep(wait).await;
+                                if state.shutting_down.load(Ordering::SeqCst) {
+                                    break;
+                                }
+                                emit_status(&app_handle, "starting");
+                                let child = spawn_dev_child(&app_handle, &api_dir, port);
+                                state.restart.lock().unwrap().last_start = Some(Instant::now());
+                                *state.child.lock().unwrap() = Some(child);
+                                // Don't declare "ready" until the respawned
+                                // process is actually serving again.
+                                tauri::async_runtime::spawn(wait_until_ready(app_handle.clone()));
+                            }
+                            None => {
+                                emit_status(&app_handle, "giving-up");
+                                break;
+                            }
+                        }
+                    }
+                });
             }
 
-            // -- Release mode: use PyInstaller sidecar --
+            // -- Release mode: use PyInstaller sidecar, supervised --
             #[cfg(not(debug_assertions))]
             {
-                let (mut rx, child) = Command::new_sidecar("axiome-api")
+                let (rx, child) = Command::new_sidecar("axiome-api")
                     .expect("failed to create sidecar command")
+                    .args(["--port", &port.to_string()])
                     .spawn()
                     .expect("failed to spawn sidecar");
 
                 let state: tauri::State<ApiState> = app.state();
+                state.restart.lock().unwrap().last_start = Some(Instant::now());
                 *state.child.lock().unwrap() = Some(child);
+                emit_status(&app_handle, "starting");
+                tauri::async_runtime::spawn(wait_until_ready(app_handle.clone()));
 
                 tauri::async_runtime::spawn(async move {
-                    while let Some(event) = rx.recv().await {
+                    let mut rx = rx;
+                    loop {
+                        let Some(event) = rx.recv().await else { break };
                         match event {
-                            CommandEvent::Stdout(line) => println!("[api] {}", line),
-                            CommandEvent::Stderr(line) => eprintln!("[api] {}", line),
+                            CommandEvent::Stdout(line) => push_log(&app_handle, "stdout", line),
+                            CommandEvent::Stderr(line) => push_log(&app_handle, "stderr", line),
+                            CommandEvent::Terminated(payload) => {
+                                let state: tauri::State<ApiState> = app_handle.state();
+                                state.child_exited.store(true, Ordering::SeqCst);
+                                if state.shutting_down.load(Ordering::SeqCst) {
+                                    break;
+                                }
+
+                                println!("[api] sidecar terminated: {:?}", payload);
+                                let wait = next_backoff(&mut state.restart.lock().unwrap());
+                                match wait {
+                                    Some(wait) => {
+                                        emit_status(&app_handle, "crashed");
+                                        tokio::time::sleep(wait).await;
+                                        if state.shutting_down.load(Ordering::SeqCst) {
+                                            break;
+                                        }
+                                        emit_status(&app_handle, "starting");
+                                        let (new_rx, new_child) = Command::new_sidecar("axiome-api")
+                                            .expect("failed to create sidecar command")
+                                            .args(["--port", &port.to_string()])
+                                            .spawn()
+                                            .expect("failed to spawn sidecar");
+                                        rx = new_rx;
+                                        state.restart.lock().unwrap().last_start = Some(Instant::now());
+                                        state.child_exited.store(false, Ordering::SeqCst);
+                                        *state.child.lock().unwrap() = Some(new_child);
+                                        // Don't declare "ready" until the respawned
+                                        // sidecar is actually serving again.
+                                        tauri::async_runtime::spawn(wait_until_ready(app_handle.clone()));
+                                    }
+                                    None => {
+                                        emit_status(&app_handle, "giving-up");
+                                        break;
+                                    }
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -83,28 +556,13 @@ fn main() {
         .on_window_event(|event| {
             if let tauri::WindowEvent::Destroyed = event.event() {
                 let state: tauri::State<ApiState> = event.window().state();
-                // Take the child out of the mutex immediately so the guard
-                // is dropped before `state`, avoiding lifetime issues.
-                let child_opt = state.child.lock().unwrap().take();
-
-                #[cfg(debug_assertions)]
-                {
-                    if let Some(child) = child_opt {
-                        // Kill the entire process tree on Windows
-                        let pid = child.id();
-                        let _ = std::process::Command::new("taskkill")
-                            .args(["/PID", &pid.to_string(), "/T", "/F"])
-                            .output();
-                        println!("[dev] Killed API process tree (PID {})", pid);
-                    }
-                }
+                state.shutting_down.store(true, Ordering::SeqCst);
 
-                #[cfg(not(debug_assertions))]
-                {
-                    if let Some(child) = child_opt {
-                        let _ = child.kill();
-                    }
-                }
+                // Give the API a chance to exit cleanly before we force it.
+                // This blocks the window-destroy callback, and with it the
+                // event-loop thread it runs on, for up to the grace period,
+                // so the process doesn't exit out from under the shutdown.
+                tauri::async_runtime::block_on(graceful_shutdown(event.window().app_handle()));
             }
         })
         .run(tauri::generate_context!())